@@ -15,18 +15,22 @@ use derive_more::Display;
 use lazy_static::lazy_static;
 use pyo3::{
     basic::CompareOp,
+    ffi,
     prelude::*,
     types::{PyFloat, PyLong},
-    PyObjectProtocol,
+    AsPyPointer, PyBufferProtocol, PyNumberProtocol, PyObjectProtocol,
 };
 use std::{
-    collections::{hash_map::DefaultHasher, HashMap},
+    collections::{hash_map::DefaultHasher, BTreeSet, HashMap},
+    convert::TryFrom,
     fmt::Debug,
     hash::{Hash, Hasher},
+    os::raw::{c_int, c_void},
+    ptr,
     sync::Mutex,
 };
 
-// TODO: __str__, __repr__, __eq__, __hash__ for PyVariable, PyObjectBehavior, PyAddress
+// TODO: __str__, __repr__ for PyVariable; __str__, __repr__, __eq__, __hash__ for PyObjectBehavior
 
 #[pymodule]
 fn core(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
@@ -34,6 +38,8 @@ fn core(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
     m.add_class::<PyVariable>()?;
     m.add_class::<PyObjectBehavior>()?;
     m.add_class::<PyAddress>()?;
+    m.add_class::<PyTimeseries>()?;
+    m.add_class::<PyFrameState>()?;
     Ok(())
 }
 
@@ -45,6 +51,339 @@ mod wafel_error {
 }
 use wafel_error::*;
 
+/// A small embedded expression language for derived/computed variables:
+/// `+ - * /`, unary negation, the functions `sqrt abs min max`, and
+/// identifiers that are looked up as ordinary variables.
+mod expr {
+    use pyo3::PyResult;
+
+    #[derive(Debug, Clone)]
+    pub enum Expr {
+        Int(i64),
+        Float(f64),
+        Ident(String),
+        Neg(Box<Expr>),
+        BinOp(BinOp, Box<Expr>, Box<Expr>),
+        Call(String, Vec<Expr>),
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    pub enum BinOp {
+        Add,
+        Sub,
+        Mul,
+        Div,
+    }
+
+    impl Expr {
+        /// Evaluate the expression, resolving identifiers through `lookup`.
+        pub fn eval(&self, lookup: &mut dyn FnMut(&str) -> PyResult<f64>) -> PyResult<f64> {
+            Ok(match self {
+                Expr::Int(n) => *n as f64,
+                Expr::Float(n) => *n,
+                Expr::Ident(name) => lookup(name)?,
+                Expr::Neg(inner) => -inner.eval(lookup)?,
+                Expr::BinOp(op, lhs, rhs) => {
+                    let lhs = lhs.eval(lookup)?;
+                    let rhs = rhs.eval(lookup)?;
+                    match op {
+                        BinOp::Add => lhs + rhs,
+                        BinOp::Sub => lhs - rhs,
+                        BinOp::Mul => lhs * rhs,
+                        BinOp::Div => lhs / rhs,
+                    }
+                }
+                Expr::Call(name, args) => {
+                    let args = args
+                        .iter()
+                        .map(|arg| arg.eval(lookup))
+                        .collect::<PyResult<Vec<f64>>>()?;
+                    match (name.as_str(), args.as_slice()) {
+                        ("sqrt", [x]) => x.sqrt(),
+                        ("abs", [x]) => x.abs(),
+                        ("min", [a, b]) => a.min(*b),
+                        ("max", [a, b]) => a.max(*b),
+                        _ => {
+                            return Err(pyo3::PyErr::new::<super::WafelError, _>(format!(
+                                "no such function: {}/{}",
+                                name,
+                                args.len()
+                            )))
+                        }
+                    }
+                }
+            })
+        }
+
+        /// Returns true if the expression always produces a floating point
+        /// result, regardless of its identifiers' underlying types (e.g. any
+        /// use of `sqrt` or `/`).
+        pub fn is_always_float(&self) -> bool {
+            match self {
+                Expr::Float(_) => true,
+                Expr::Int(_) | Expr::Ident(_) => false,
+                Expr::Neg(inner) => inner.is_always_float(),
+                Expr::BinOp(BinOp::Div, _, _) => true,
+                Expr::BinOp(_, lhs, rhs) => lhs.is_always_float() || rhs.is_always_float(),
+                Expr::Call(name, args) => name == "sqrt" || args.iter().any(Expr::is_always_float),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Token {
+        Num(f64, bool),
+        Ident(String),
+        Plus,
+        Minus,
+        Star,
+        Slash,
+        LParen,
+        RParen,
+        Comma,
+    }
+
+    fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+        let mut tokens = Vec::new();
+        let mut chars = input.chars().peekable();
+
+        while let Some(&c) = chars.peek() {
+            match c {
+                c if c.is_whitespace() => {
+                    chars.next();
+                }
+                '+' => {
+                    chars.next();
+                    tokens.push(Token::Plus);
+                }
+                '-' => {
+                    chars.next();
+                    tokens.push(Token::Minus);
+                }
+                '*' => {
+                    chars.next();
+                    tokens.push(Token::Star);
+                }
+                '/' => {
+                    chars.next();
+                    tokens.push(Token::Slash);
+                }
+                '(' => {
+                    chars.next();
+                    tokens.push(Token::LParen);
+                }
+                ')' => {
+                    chars.next();
+                    tokens.push(Token::RParen);
+                }
+                ',' => {
+                    chars.next();
+                    tokens.push(Token::Comma);
+                }
+                c if c.is_ascii_digit() || c == '.' => {
+                    let mut number = String::new();
+                    let mut has_dot = false;
+                    while let Some(&c) = chars.peek() {
+                        if c.is_ascii_digit() {
+                            number.push(c);
+                            chars.next();
+                        } else if c == '.' && !has_dot {
+                            has_dot = true;
+                            number.push(c);
+                            chars.next();
+                        } else {
+                            break;
+                        }
+                    }
+                    let value: f64 = number
+                        .parse()
+                        .map_err(|_| format!("invalid number: {}", number))?;
+                    tokens.push(Token::Num(value, has_dot));
+                }
+                c if c.is_alphabetic() || c == '_' => {
+                    let mut ident = String::new();
+                    while let Some(&c) = chars.peek() {
+                        if c.is_alphanumeric() || c == '_' {
+                            ident.push(c);
+                            chars.next();
+                        } else {
+                            break;
+                        }
+                    }
+                    tokens.push(Token::Ident(ident));
+                }
+                c => return Err(format!("unexpected character: {}", c)),
+            }
+        }
+
+        Ok(tokens)
+    }
+
+    struct Parser {
+        tokens: Vec<Token>,
+        pos: usize,
+    }
+
+    impl Parser {
+        fn peek(&self) -> Option<&Token> {
+            self.tokens.get(self.pos)
+        }
+
+        fn advance(&mut self) -> Option<Token> {
+            let token = self.tokens.get(self.pos).cloned();
+            self.pos += 1;
+            token
+        }
+
+        fn parse_expr(&mut self) -> Result<Expr, String> {
+            let mut lhs = self.parse_term()?;
+            loop {
+                let op = match self.peek() {
+                    Some(Token::Plus) => BinOp::Add,
+                    Some(Token::Minus) => BinOp::Sub,
+                    _ => break,
+                };
+                self.advance();
+                let rhs = self.parse_term()?;
+                lhs = Expr::BinOp(op, Box::new(lhs), Box::new(rhs));
+            }
+            Ok(lhs)
+        }
+
+        fn parse_term(&mut self) -> Result<Expr, String> {
+            let mut lhs = self.parse_unary()?;
+            loop {
+                let op = match self.peek() {
+                    Some(Token::Star) => BinOp::Mul,
+                    Some(Token::Slash) => BinOp::Div,
+                    _ => break,
+                };
+                self.advance();
+                let rhs = self.parse_unary()?;
+                lhs = Expr::BinOp(op, Box::new(lhs), Box::new(rhs));
+            }
+            Ok(lhs)
+        }
+
+        fn parse_unary(&mut self) -> Result<Expr, String> {
+            if let Some(Token::Minus) = self.peek() {
+                self.advance();
+                return Ok(Expr::Neg(Box::new(self.parse_unary()?)));
+            }
+            self.parse_atom()
+        }
+
+        fn parse_atom(&mut self) -> Result<Expr, String> {
+            match self.advance() {
+                Some(Token::Num(n, has_dot)) => Ok(if has_dot {
+                    Expr::Float(n)
+                } else {
+                    Expr::Int(n as i64)
+                }),
+                Some(Token::Ident(name)) => {
+                    if self.peek() == Some(&Token::LParen) {
+                        self.advance();
+                        let mut args = Vec::new();
+                        if self.peek() != Some(&Token::RParen) {
+                            args.push(self.parse_expr()?);
+                            while self.peek() == Some(&Token::Comma) {
+                                self.advance();
+                                args.push(self.parse_expr()?);
+                            }
+                        }
+                        if self.advance() != Some(Token::RParen) {
+                            return Err("expected closing paren after call".to_owned());
+                        }
+                        Ok(Expr::Call(name, args))
+                    } else {
+                        Ok(Expr::Ident(name))
+                    }
+                }
+                Some(Token::LParen) => {
+                    let inner = self.parse_expr()?;
+                    if self.advance() != Some(Token::RParen) {
+                        return Err("expected closing paren".to_owned());
+                    }
+                    Ok(inner)
+                }
+                other => Err(format!("unexpected token: {:?}", other)),
+            }
+        }
+    }
+
+    /// Parse a formula like `sqrt(vel_x*vel_x + vel_z*vel_z)` into an [`Expr`].
+    pub fn parse(input: &str) -> Result<Expr, String> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.parse_expr()?;
+        if parser.pos != parser.tokens.len() {
+            return Err(format!("unexpected trailing input in: {}", input));
+        }
+        Ok(expr)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn eval_str(input: &str, lookup: &mut dyn FnMut(&str) -> PyResult<f64>) -> f64 {
+            parse(input).unwrap().eval(lookup).unwrap()
+        }
+
+        #[test]
+        fn evaluates_operator_precedence() {
+            assert_eq!(eval_str("2 + 3 * 4", &mut |_| unreachable!()), 14.0);
+            assert_eq!(eval_str("(2 + 3) * 4", &mut |_| unreachable!()), 20.0);
+            assert_eq!(eval_str("2 * 3 + 4 / 2", &mut |_| unreachable!()), 8.0);
+        }
+
+        #[test]
+        fn evaluates_unary_minus() {
+            assert_eq!(eval_str("-3 + 5", &mut |_| unreachable!()), 2.0);
+            assert_eq!(eval_str("-(1 + 2)", &mut |_| unreachable!()), -3.0);
+        }
+
+        #[test]
+        fn evaluates_function_calls() {
+            assert_eq!(eval_str("sqrt(9)", &mut |_| unreachable!()), 3.0);
+            assert_eq!(eval_str("min(1, 2)", &mut |_| unreachable!()), 1.0);
+            assert_eq!(eval_str("max(1, 2)", &mut |_| unreachable!()), 2.0);
+        }
+
+        #[test]
+        fn looks_up_identifiers() {
+            let value = eval_str("vel_x * vel_x", &mut |name| {
+                assert_eq!(name, "vel_x");
+                Ok(2.0)
+            });
+            assert_eq!(value, 4.0);
+        }
+
+        #[test]
+        fn rejects_wrong_function_arity() {
+            let expr = parse("sqrt(1, 2)").unwrap();
+            assert!(expr.eval(&mut |_| unreachable!()).is_err());
+        }
+
+        #[test]
+        fn rejects_trailing_input() {
+            assert!(parse("1 + 2 )").is_err());
+        }
+
+        #[test]
+        fn rejects_unexpected_character() {
+            assert!(parse("1 + @").is_err());
+        }
+
+        #[test]
+        fn is_always_float_accounts_for_division_and_sqrt() {
+            assert!(!parse("1 + 2").unwrap().is_always_float());
+            assert!(parse("1 / 2").unwrap().is_always_float());
+            assert!(parse("sqrt(x)").unwrap().is_always_float());
+        }
+    }
+}
+
 impl From<Error> for PyErr {
     fn from(err: Error) -> PyErr {
         PyErr::new::<WafelError, _>(err.to_string())
@@ -71,6 +410,15 @@ pub struct PyPipeline {
 struct ValidPipeline {
     pipeline: Pipeline<dll::Memory>,
     symbols_by_address: HashMap<dll::Address, String>,
+    prefetch: Option<FramePrefetcher>,
+    behavior_callbacks: HashMap<ObjectBehavior, PyObject>,
+    derived_variables: HashMap<String, DerivedVariable>,
+}
+
+#[derive(Debug, Clone)]
+struct DerivedVariable {
+    expr: expr::Expr,
+    group: String,
 }
 
 impl PyPipeline {
@@ -86,6 +434,9 @@ impl PyPipeline {
             valid: Some(ValidPipeline {
                 pipeline,
                 symbols_by_address,
+                prefetch: None,
+                behavior_callbacks: HashMap::new(),
+                derived_variables: HashMap::new(),
             }),
         })
     }
@@ -144,34 +495,119 @@ impl PyPipeline {
     /// Read a variable.
     ///
     /// If the variable is a data variable, the value will be read from memory
-    /// on the variable's frame.
+    /// on the variable's frame. If it's a derived variable (see
+    /// `define_variable`), its formula is re-evaluated on the variable's frame.
     pub fn read(&self, py: Python<'_>, variable: &PyVariable) -> PyResult<PyObject> {
+        if self.get().derived_variables.contains_key(variable.name()) {
+            let frame = variable.variable.frame.ok_or_else(|| {
+                PyErr::new::<WafelError, _>("derived variables must be read on a frame")
+            })?;
+            let value = self.read_value(variable, frame)?;
+            return value_to_py_object(py, &value);
+        }
+
         let value = self.get().pipeline.read(&variable.variable)?;
         let py_object = value_to_py_object(py, &value)?;
         Ok(py_object)
     }
 
+    /// Read `variable` on `frame`, resolving derived variables (see
+    /// `define_variable`) the same way `read` does.
+    ///
+    /// Shared with `read_timeseries` so every read path honors derived
+    /// variables identically instead of each needing its own special case.
+    fn read_value(&self, variable: &PyVariable, frame: u32) -> PyResult<Value> {
+        match self.get().derived_variables.get(variable.name()) {
+            Some(derived) => {
+                let value = derived.expr.eval(&mut |name| match self
+                    .get()
+                    .pipeline
+                    .read(&Variable::new(name).with_frame(frame))?
+                {
+                    Value::Int(n) => Ok(n as f64),
+                    Value::Float(n) => Ok(n),
+                    value => Err(Error::from(SM64ErrorCause::ValueToPython {
+                        value: value.to_string(),
+                    })
+                    .into()),
+                })?;
+                Ok(if derived.expr.is_always_float() {
+                    Value::Float(value)
+                } else {
+                    Value::Int(value as i64)
+                })
+            }
+            None => Ok(self
+                .get()
+                .pipeline
+                .read(&variable.variable.with_frame(frame))?),
+        }
+    }
+
     /// Write a variable.
     ///
     /// If the variable is a data variable, the value will be truncated and written
-    /// to memory on the variable's frame.
+    /// to memory on the variable's frame. Derived variables are read-only and
+    /// raise an error.
     pub fn write(
         &mut self,
         py: Python<'_>,
         variable: &PyVariable,
         value: PyObject,
     ) -> PyResult<()> {
+        if self.get().derived_variables.contains_key(variable.name()) {
+            return Err(PyErr::new::<WafelError, _>(format!(
+                "cannot write to derived variable {}",
+                variable.name()
+            )));
+        }
         let value = py_object_to_value(py, &value)?;
         self.get_mut().pipeline.write(&variable.variable, &value)?;
         Ok(())
     }
 
     /// Reset a variable.
+    ///
+    /// Derived variables are read-only and raise an error.
     pub fn reset(&mut self, variable: &PyVariable) -> PyResult<()> {
+        if self.get().derived_variables.contains_key(variable.name()) {
+            return Err(PyErr::new::<WafelError, _>(format!(
+                "cannot reset derived variable {}",
+                variable.name()
+            )));
+        }
         self.get_mut().pipeline.reset(&variable.variable)?;
         Ok(())
     }
 
+    /// Register a derived, read-only variable computed from a formula over
+    /// existing data variables, e.g.
+    /// `define_variable("mario.hspeed", "sqrt(vel_x*vel_x + vel_z*vel_z)", "mario")`.
+    ///
+    /// The formula supports `+ - * /`, unary negation, the functions
+    /// `sqrt abs min max`, and identifiers that are looked up as ordinary
+    /// variables on the same frame as the derived variable. Derived variables
+    /// are re-evaluated lazily every time they're read, grouped under
+    /// `variable_group`, and cannot be written to or reset.
+    pub fn define_variable(
+        &mut self,
+        name: &str,
+        expression: &str,
+        variable_group: &str,
+    ) -> PyResult<()> {
+        let expr = expr::parse(expression).map_err(|message| {
+            PyErr::new::<WafelError, _>(format!("invalid expression for {}: {}", name, message))
+        })?;
+        self.get_mut().derived_variables.insert(
+            name.to_owned(),
+            DerivedVariable {
+                expr,
+                group: variable_group.to_owned(),
+            },
+        );
+        Ok(())
+    }
+
     /// Get the address for the given path.
     pub fn path_address(&self, frame: u32, path: &str) -> PyResult<PyAddress> {
         let state = self.get().pipeline.timeline().frame(frame)?;
@@ -187,6 +623,89 @@ impl PyPipeline {
         Ok(py_object)
     }
 
+    /// Read a typed value directly at an address, with `type_name` resolved
+    /// through the data layout (e.g. `"f32"`). Only integer, float and pointer
+    /// values can be returned; reading a struct or array type raises an error.
+    ///
+    /// Unlike `path_read`, this works for addresses that have no named path,
+    /// e.g. elements of a linked list or array walked via `follow_pointer`.
+    pub fn read_address(
+        &self,
+        py: Python<'_>,
+        frame: u32,
+        address: &PyAddress,
+        type_name: &str,
+    ) -> PyResult<PyObject> {
+        let timeline = self.get().pipeline.timeline();
+        let state = timeline.frame(frame)?;
+        let data_type = timeline.memory().data_layout().data_type(type_name)?;
+        let value = state.address_read(&address.address, &data_type)?;
+        value_to_py_object(py, &value)
+    }
+
+    /// Follow the pointer stored at `address`, returning the address it
+    /// points to.
+    pub fn follow_pointer(&self, frame: u32, address: &PyAddress) -> PyResult<PyAddress> {
+        let state = self.get().pipeline.timeline().frame(frame)?;
+        let address = state.address_read_pointer(&address.address)?;
+        Ok(PyAddress { address })
+    }
+
+    /// Read a variable across a contiguous range of frames, returning a buffer
+    /// that numpy can wrap without copying.
+    ///
+    /// This avoids the overhead of one Python call per frame when scanning a
+    /// variable across many frames, e.g. for plotting or desync detection.
+    pub fn read_timeseries(
+        &self,
+        variable: &PyVariable,
+        start_frame: u32,
+        end_frame: u32,
+    ) -> PyResult<PyTimeseries> {
+        if end_frame < start_frame {
+            return Err(PyErr::new::<WafelError, _>(format!(
+                "end_frame {} is before start_frame {}",
+                end_frame, start_frame
+            )));
+        }
+
+        let is_float = self.is_float(variable)?;
+
+        let data = if is_float {
+            let mut values = Vec::with_capacity((end_frame - start_frame) as usize);
+            for frame in start_frame..end_frame {
+                values.push(match self.read_value(variable, frame)? {
+                    Value::Float(value) => value,
+                    Value::Int(value) => value as f64,
+                    value => {
+                        return Err(Error::from(SM64ErrorCause::ValueToPython {
+                            value: value.to_string(),
+                        })
+                        .into())
+                    }
+                });
+            }
+            TimeseriesData::Float(values)
+        } else {
+            let mut values = Vec::with_capacity((end_frame - start_frame) as usize);
+            for frame in start_frame..end_frame {
+                values.push(match self.read_value(variable, frame)? {
+                    Value::Int(value) => value,
+                    Value::Float(value) => value as i64,
+                    value => {
+                        return Err(Error::from(SM64ErrorCause::ValueToPython {
+                            value: value.to_string(),
+                        })
+                        .into())
+                    }
+                });
+            }
+            TimeseriesData::Int(values)
+        };
+
+        Ok(PyTimeseries::new(data))
+    }
+
     /// Insert a new state at the given frame, shifting edits forward.
     pub fn insert_frame(&mut self, frame: u32) {
         self.get_mut().pipeline.insert_frame(frame);
@@ -214,8 +733,90 @@ impl PyPipeline {
         Ok(())
     }
 
+    /// Request that the frames in `[start, end)` be materialized by
+    /// subsequent `pump_prefetch` calls, so that later `read`/`path_read`
+    /// calls for those frames return immediately instead of blocking on a
+    /// slot-advance.
+    ///
+    /// This only records which frames to prioritize; no work happens until
+    /// `pump_prefetch` is called, and none of it happens off the caller's
+    /// thread (`dll::Memory` is `unsendable`, so there is no background
+    /// worker here). Callers are expected to call `pump_prefetch` themselves,
+    /// e.g. on a UI idle tick, ahead of when the requested frames are needed.
+    ///
+    /// Frames that `pump_prefetch` has already materialized (those listed by
+    /// `frames_ready`) are dropped from the request.
+    /// Pass a `callback` to be notified each time a batch of requested frames
+    /// finishes prefetching; pass `None` to just poll `frames_ready` instead.
+    pub fn request_frames(&mut self, start: u32, end: u32, callback: Option<PyObject>) {
+        let valid = self.get_mut();
+        let prefetch = valid.prefetch.get_or_insert_with(FramePrefetcher::new);
+        prefetch.callback = callback;
+        prefetch.request(start, end);
+    }
+
+    /// Materialize previously requested frames for up to
+    /// `max_run_time_seconds`, same pacing as `balance_distribution`.
+    ///
+    /// `dll::Memory` is `unsendable`, so a requested frame can only ever be
+    /// advanced on the pipeline's own thread — there is no way to do that
+    /// work on a background thread. Instead, latency is hidden by spreading
+    /// it across many short calls: call this periodically (e.g. once per UI
+    /// idle tick) while scrolling or before playback reaches a requested
+    /// range, rather than in one large blocking batch when a frame is
+    /// actually needed.
+    pub fn pump_prefetch(&mut self, py: Python<'_>, max_run_time_seconds: f32) -> PyResult<()> {
+        let deadline =
+            std::time::Instant::now() + std::time::Duration::from_secs_f32(max_run_time_seconds);
+
+        let valid = self.get_mut();
+        let prefetch = match &mut valid.prefetch {
+            Some(prefetch) => prefetch,
+            None => return Ok(()),
+        };
+
+        let mut newly_ready = Vec::new();
+        while std::time::Instant::now() < deadline {
+            let frame = match prefetch.pending.iter().next().copied() {
+                Some(frame) => frame,
+                None => break,
+            };
+            prefetch.pending.remove(&frame);
+            valid.pipeline.timeline().frame(frame)?;
+            prefetch.ready.insert(frame);
+            newly_ready.push(frame);
+        }
+
+        if !newly_ready.is_empty() {
+            if let Some(callback) = &prefetch.callback {
+                callback.call1(py, (newly_ready,))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Return the frames in `[start, end)` that have already been
+    /// materialized by `pump_prefetch`.
+    ///
+    /// A frame stays in this list once it has been materialized; later
+    /// `write`, `reset`, `insert_frame` and `delete_frame` calls do not remove
+    /// it, so reading a listed frame can still block on a slot-advance.
+    pub fn frames_ready(&self, start: u32, end: u32) -> Vec<u32> {
+        match &self.get().prefetch {
+            Some(prefetch) => (start..end)
+                .filter(|f| prefetch.ready.contains(f))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
     /// Return the label for the variable if it has one.
     pub fn label(&self, variable: &PyVariable) -> PyResult<Option<&str>> {
+        if self.get().derived_variables.contains_key(variable.name()) {
+            return Ok(Some(variable.name()));
+        }
+
         let label = self
             .get()
             .pipeline
@@ -226,6 +827,10 @@ impl PyPipeline {
 
     /// Return true if the variable has an integer data type.
     pub fn is_int(&self, variable: &PyVariable) -> PyResult<bool> {
+        if let Some(derived) = self.get().derived_variables.get(variable.name()) {
+            return Ok(!derived.expr.is_always_float());
+        }
+
         Ok(self
             .get()
             .pipeline
@@ -236,6 +841,10 @@ impl PyPipeline {
 
     /// Return true if the variable has a float data type.
     pub fn is_float(&self, variable: &PyVariable) -> PyResult<bool> {
+        if let Some(derived) = self.get().derived_variables.get(variable.name()) {
+            return Ok(derived.expr.is_always_float());
+        }
+
         Ok(self
             .get()
             .pipeline
@@ -246,6 +855,10 @@ impl PyPipeline {
 
     /// Return true if the variable is a bit flag.
     pub fn is_bit_flag(&self, variable: &PyVariable) -> PyResult<bool> {
+        if self.get().derived_variables.contains_key(variable.name()) {
+            return Ok(false);
+        }
+
         Ok(self
             .get()
             .pipeline
@@ -256,12 +869,25 @@ impl PyPipeline {
 
     /// Get the variables
     fn variable_group(&self, group: &str) -> Vec<PyVariable> {
-        self.get()
+        let mut variables: Vec<PyVariable> = self
+            .get()
             .pipeline
             .data_variables()
             .group(group)
             .map(|variable| PyVariable { variable })
-            .collect()
+            .collect();
+
+        variables.extend(
+            self.get()
+                .derived_variables
+                .iter()
+                .filter(|(_, derived)| derived.group == group)
+                .map(|(name, _)| PyVariable {
+                    variable: Variable::new(name),
+                }),
+        );
+
+        variables
     }
 
     /// Translate an address into a raw pointer into the base slot.
@@ -342,6 +968,195 @@ impl PyPipeline {
             format!("Object[{}]", address)
         }
     }
+
+    /// Register a callback to be invoked during `run` for every active
+    /// object whose behavior matches `behavior_name` (e.g. `"bhvBobomb"`).
+    pub fn register_behavior_callback(
+        &mut self,
+        behavior_name: &str,
+        callback: PyObject,
+    ) -> PyResult<()> {
+        let valid = self.get_mut();
+        let address = valid
+            .symbols_by_address
+            .iter()
+            .find(|(_, symbol)| symbol.as_str() == behavior_name)
+            .map(|(address, _)| *address)
+            .ok_or_else(|| {
+                PyErr::new::<WafelError, _>(format!("unknown behavior symbol: {}", behavior_name))
+            })?;
+
+        valid
+            .behavior_callbacks
+            .insert(ObjectBehavior(address.into()), callback);
+        Ok(())
+    }
+
+    /// Advance the timeline one frame at a time from `start_frame` to
+    /// `end_frame`, calling `callback(frame, state)` on each frame with a
+    /// read-only state handle.
+    ///
+    /// The callback may return a `{Variable: value}` dict of writes to apply
+    /// before the next frame is advanced. Any callback registered with
+    /// `register_behavior_callback` is also invoked for each active object
+    /// whose behavior matches, before `callback` runs for that frame.
+    ///
+    /// If the callback raises, every variable written by this call is
+    /// restored to its value from before this call (not reset), so a pre-
+    /// existing edit on a variable survives a failed search instead of being
+    /// wiped along with it.
+    pub fn run(
+        slf: PyRefMut<'_, Self>,
+        py: Python<'_>,
+        start_frame: u32,
+        end_frame: u32,
+        callback: PyObject,
+    ) -> PyResult<()> {
+        let pipeline_py: Py<Self> = slf.into();
+        let mut snapshots: HashMap<Variable, Value> = HashMap::new();
+
+        let result = (|| -> PyResult<()> {
+            for frame in start_frame..end_frame {
+                Self::run_behavior_callbacks(&pipeline_py, py, frame)?;
+
+                let state = Py::new(
+                    py,
+                    PyFrameState {
+                        pipeline: pipeline_py.clone(),
+                        frame,
+                    },
+                )?;
+                let writes = callback.call1(py, (frame, state))?;
+
+                if !writes.is_none(py) {
+                    let writes: HashMap<PyVariable, PyObject> = writes.extract(py)?;
+                    for (variable, value) in writes {
+                        let variable = variable.with_frame(frame);
+                        let is_derived = pipeline_py
+                            .borrow(py)
+                            .get()
+                            .derived_variables
+                            .contains_key(variable.name());
+                        if !is_derived && !snapshots.contains_key(&variable.variable) {
+                            let prior = pipeline_py
+                                .borrow(py)
+                                .get()
+                                .pipeline
+                                .read(&variable.variable)?;
+                            snapshots.insert(variable.variable.clone(), prior);
+                        }
+                        // Route through `write` (not the raw `pipeline`) so a
+                        // callback that targets a derived variable raises
+                        // here, the same as any other write path.
+                        pipeline_py.borrow_mut(py).write(py, &variable, value)?;
+                    }
+                }
+            }
+            Ok(())
+        })();
+
+        if result.is_err() {
+            let mut pipeline = pipeline_py.borrow_mut(py);
+            for (variable, value) in &snapshots {
+                let _ = pipeline.get_mut().pipeline.write(variable, value);
+            }
+        }
+
+        result
+    }
+
+    fn run_behavior_callbacks(pipeline_py: &Py<Self>, py: Python<'_>, frame: u32) -> PyResult<()> {
+        // SM64 has a fixed pool of 240 object slots.
+        const NUM_OBJECT_SLOTS: usize = 240;
+
+        if pipeline_py.borrow(py).get().behavior_callbacks.is_empty() {
+            return Ok(());
+        }
+
+        for object in 0..NUM_OBJECT_SLOTS {
+            let behavior = match pipeline_py.borrow(py).object_behavior(frame, object)? {
+                Some(behavior) => behavior,
+                None => continue,
+            };
+
+            let callback = pipeline_py
+                .borrow(py)
+                .get()
+                .behavior_callbacks
+                .get(&behavior.behavior)
+                .map(|callback| callback.clone_ref(py));
+
+            if let Some(callback) = callback {
+                callback.call1(py, (object,))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A read-only view of a single frame's state, passed to `PyPipeline::run`
+/// callbacks.
+///
+/// Writes aren't exposed here; a `run` callback instead returns a dict of
+/// `{Variable: value}` writes for `run` to apply once the callback returns.
+#[pyclass(name = FrameState, unsendable)]
+#[derive(Debug)]
+pub struct PyFrameState {
+    pipeline: Py<PyPipeline>,
+    frame: u32,
+}
+
+#[pymethods]
+impl PyFrameState {
+    /// Get the frame number this state corresponds to.
+    #[getter]
+    pub fn frame(&self) -> u32 {
+        self.frame
+    }
+
+    /// Read a variable, bound to this frame.
+    pub fn read(&self, py: Python<'_>, variable: &PyVariable) -> PyResult<PyObject> {
+        let variable = variable.with_frame(self.frame);
+        self.pipeline.borrow(py).read(py, &variable)
+    }
+
+    /// Read from the given path on this frame.
+    pub fn path_read(&self, py: Python<'_>, path: &str) -> PyResult<PyObject> {
+        self.pipeline.borrow(py).path_read(py, self.frame, path)
+    }
+}
+
+/// Tracks the frames that have been requested for prefetching but not yet
+/// materialized, and those that have.
+///
+/// `dll::Memory` is `unsendable`, so there is no thread but the pipeline's own
+/// that could ever do the actual slot-advance; `PyPipeline::pump_prefetch`
+/// works through `pending` in small time-boxed batches instead, the same
+/// pacing `balance_distribution` uses for hotspot housekeeping.
+#[derive(Debug)]
+struct FramePrefetcher {
+    pending: BTreeSet<u32>,
+    ready: BTreeSet<u32>,
+    callback: Option<PyObject>,
+}
+
+impl FramePrefetcher {
+    fn new() -> Self {
+        Self {
+            pending: BTreeSet::new(),
+            ready: BTreeSet::new(),
+            callback: None,
+        }
+    }
+
+    fn request(&mut self, start: u32, end: u32) {
+        for frame in start..end {
+            if !self.ready.contains(&frame) {
+                self.pending.insert(frame);
+            }
+        }
+    }
 }
 
 /// An abstract game variable.
@@ -478,13 +1293,211 @@ pub struct PyObjectBehavior {
     behavior: ObjectBehavior,
 }
 
-/// An opaque representation of a memory address.
+/// A memory address.
+///
+/// Addresses can be offset like raw pointers (`address + n`, `address - n`)
+/// and subtracted from one another to get the byte delta between them, which
+/// lets scripts walk linked structures and arrays that have no named path.
 #[pyclass(name = Address, unsendable)]
 #[derive(Debug, Clone)]
 pub struct PyAddress {
     address: dll::Address,
 }
 
+#[pyproto]
+impl PyObjectProtocol for PyAddress {
+    fn __repr__(&self) -> String {
+        format!("Address({:#x})", usize::from(self.address))
+    }
+
+    fn __richcmp__(&self, other: PyAddress, op: CompareOp) -> PyResult<bool> {
+        let lhs = usize::from(self.address);
+        let rhs = usize::from(other.address);
+        Ok(match op {
+            CompareOp::Eq => lhs == rhs,
+            CompareOp::Ne => lhs != rhs,
+            CompareOp::Lt => lhs < rhs,
+            CompareOp::Le => lhs <= rhs,
+            CompareOp::Gt => lhs > rhs,
+            CompareOp::Ge => lhs >= rhs,
+        })
+    }
+
+    fn __hash__(&self) -> PyResult<isize> {
+        Ok(usize::from(self.address) as isize)
+    }
+}
+
+#[pyproto]
+impl PyNumberProtocol for PyAddress {
+    fn __add__(lhs: PyAddress, rhs: i64) -> PyResult<PyAddress> {
+        let base = usize::from(lhs.address) as i64;
+        let address = checked_offset(base, rhs)?;
+        Ok(PyAddress {
+            address: address.into(),
+        })
+    }
+
+    /// `address - n` offsets the address backward by `n` bytes; `addr_a -
+    /// addr_b` yields the integer byte delta between the two addresses.
+    fn __sub__(lhs: PyAddress, rhs: &PyAny) -> PyResult<PyObject> {
+        let py = rhs.py();
+        if let Ok(other) = rhs.extract::<PyRef<PyAddress>>() {
+            let lhs_addr = usize::from(lhs.address) as i64;
+            let rhs_addr = usize::from(other.address) as i64;
+            let delta = lhs_addr.checked_sub(rhs_addr).ok_or_else(|| {
+                PyErr::new::<WafelError, _>(format!(
+                    "address delta overflow: {:#x} - {:#x}",
+                    lhs_addr, rhs_addr
+                ))
+            })?;
+            Ok(delta.to_object(py))
+        } else {
+            let delta: i64 = rhs.extract()?;
+            let base = usize::from(lhs.address) as i64;
+            let raw = base.checked_sub(delta).ok_or_else(|| {
+                PyErr::new::<WafelError, _>(format!("address underflow: {:#x} - {}", base, delta))
+            })?;
+            let address = usize::try_from(raw).map_err(|_| {
+                PyErr::new::<WafelError, _>(format!("address underflow: {:#x} - {}", base, delta))
+            })?;
+            Ok(PyAddress {
+                address: address.into(),
+            }
+            .into_py(py))
+        }
+    }
+}
+
+/// Offset `base` by `delta` bytes, raising `WafelError` instead of panicking
+/// or silently wrapping on overflow or underflow.
+///
+/// `PyAddress` arithmetic is driven by script-provided offsets, so it can't
+/// trust `delta` to stay in range the way internal pointer math can.
+fn checked_offset(base: i64, delta: i64) -> PyResult<usize> {
+    let address = base.checked_add(delta).ok_or_else(|| {
+        PyErr::new::<WafelError, _>(format!("address overflow: {:#x} + {}", base, delta))
+    })?;
+    usize::try_from(address).map_err(|_| {
+        PyErr::new::<WafelError, _>(format!("address underflow: {:#x} + {}", base, delta))
+    })
+}
+
+/// A packed, contiguous run of values for a single variable across a frame
+/// range.
+///
+/// Implements the buffer protocol so that numpy can wrap the underlying
+/// `Vec` directly instead of copying it element by element.
+#[pyclass(name = Timeseries, unsendable)]
+#[derive(Debug)]
+pub struct PyTimeseries {
+    data: TimeseriesData,
+    // Sized once at construction and reused by every `bf_getbuffer` call, so
+    // that repeated `memoryview`/`np.frombuffer` pulls (the hot path this
+    // type exists for) don't leak a `shape`/`strides` allocation each time.
+    shape: [isize; 1],
+    strides: [isize; 1],
+}
+
+impl PyTimeseries {
+    fn new(data: TimeseriesData) -> Self {
+        let len = data.len();
+        Self {
+            data,
+            shape: [len as isize],
+            strides: [8],
+        }
+    }
+}
+
+#[derive(Debug)]
+enum TimeseriesData {
+    Int(Vec<i64>),
+    Float(Vec<f64>),
+}
+
+impl TimeseriesData {
+    fn len(&self) -> usize {
+        match self {
+            TimeseriesData::Int(values) => values.len(),
+            TimeseriesData::Float(values) => values.len(),
+        }
+    }
+
+    fn as_raw_parts(&mut self) -> (*mut c_void, usize, &'static [u8]) {
+        match self {
+            TimeseriesData::Int(values) => {
+                (values.as_mut_ptr() as *mut c_void, values.len(), b"q\0")
+            }
+            TimeseriesData::Float(values) => {
+                (values.as_mut_ptr() as *mut c_void, values.len(), b"d\0")
+            }
+        }
+    }
+}
+
+#[pyproto]
+impl PyBufferProtocol for PyTimeseries {
+    fn bf_getbuffer(
+        mut slf: PyRefMut<Self>,
+        view: *mut ffi::Py_buffer,
+        flags: c_int,
+    ) -> PyResult<()> {
+        if view.is_null() {
+            return Err(pyo3::exceptions::BufferError::py_err("view is null"));
+        }
+
+        let (buf, len, format) = slf.data.as_raw_parts();
+        let itemsize = 8;
+
+        unsafe {
+            // `view.obj` is what keeps `slf` (and therefore the backing `Vec`)
+            // alive for as long as a memoryview/numpy array built from this
+            // buffer exists. It must be an owned (incref'd) reference, released
+            // again in `bf_releasebuffer`.
+            let obj = slf.as_ptr();
+            ffi::Py_INCREF(obj);
+            (*view).obj = obj;
+            (*view).buf = buf;
+            (*view).len = (len * itemsize) as isize;
+            (*view).readonly = 0;
+            (*view).itemsize = itemsize as isize;
+            (*view).ndim = 1;
+            (*view).shape = if (flags & ffi::PyBUF_ND) != 0 {
+                slf.shape.as_mut_ptr()
+            } else {
+                ptr::null_mut()
+            };
+            (*view).strides = if (flags & ffi::PyBUF_STRIDES) != 0 {
+                slf.strides.as_mut_ptr()
+            } else {
+                ptr::null_mut()
+            };
+            (*view).suboffsets = ptr::null_mut();
+            (*view).internal = ptr::null_mut();
+            (*view).format = if (flags & ffi::PyBUF_FORMAT) != 0 {
+                format.as_ptr() as *mut std::os::raw::c_char
+            } else {
+                ptr::null_mut()
+            };
+        }
+
+        Ok(())
+    }
+
+    // The backing `Vec` itself is freed when `slf` is dropped; this only
+    // needs to release the `view.obj` reference taken in `bf_getbuffer`. Per
+    // pyo3's guidance, this must never fail.
+    fn bf_releasebuffer(_slf: PyRefMut<Self>, view: *mut ffi::Py_buffer) {
+        unsafe {
+            if !(*view).obj.is_null() {
+                ffi::Py_DECREF((*view).obj);
+                (*view).obj = ptr::null_mut();
+            }
+        }
+    }
+}
+
 fn value_to_py_object(py: Python<'_>, value: &Value) -> PyResult<PyObject> {
     match value {
         Value::Int(n) => Ok(n.to_object(py)),